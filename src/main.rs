@@ -1,9 +1,245 @@
-use std::io;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+
+/// An exact rational number, always kept reduced to lowest terms with a
+/// positive denominator (the zero value is normalised to `0/1`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rational {
+    num: i128,
+    den: i128,
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        let t = b;
+        b = a % b;
+        a = t;
+    }
+    a
+}
+
+impl Rational {
+    fn new(num: i128, den: i128) -> Self {
+        debug_assert!(den != 0, "Rational denominator must not be zero");
+        let sign = if den < 0 { -1 } else { 1 };
+        let (num, den) = (num * sign, den * sign);
+        if num == 0 {
+            return Rational { num: 0, den: 1 };
+        }
+        let g = gcd(num, den);
+        Rational {
+            num: num / g,
+            den: den / g,
+        }
+    }
+
+    fn from_int(value: i128) -> Self {
+        Rational { num: value, den: 1 }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.num == 0
+    }
+
+    fn checked_add(self, other: Rational) -> Option<Rational> {
+        let num = (self.num.checked_mul(other.den)?).checked_add(other.num.checked_mul(self.den)?)?;
+        let den = self.den.checked_mul(other.den)?;
+        Some(Rational::new(num, den))
+    }
+
+    fn checked_sub(self, other: Rational) -> Option<Rational> {
+        self.checked_add(Rational::new(other.num.checked_neg()?, other.den))
+    }
+
+    fn checked_mul(self, other: Rational) -> Option<Rational> {
+        let num = self.num.checked_mul(other.num)?;
+        let den = self.den.checked_mul(other.den)?;
+        Some(Rational::new(num, den))
+    }
+
+    fn checked_div(self, other: Rational) -> Option<Rational> {
+        let num = self.num.checked_mul(other.den)?;
+        let den = self.den.checked_mul(other.num)?;
+        Some(Rational::new(num, den))
+    }
+
+    fn checked_neg(self) -> Option<Rational> {
+        Some(Rational::new(self.num.checked_neg()?, self.den))
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+/// A complex number over `f64`. Unlike `Rational`, arithmetic here is
+/// floating-point, so it is only reached for once an operand is genuinely
+/// complex; pure-real/-rational expressions stay in `Number::Rational`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn add(self, other: Complex) -> Complex {
+        Complex {
+            re: self.re + other.re,
+            im: self.im + other.im,
+        }
+    }
+
+    fn sub(self, other: Complex) -> Complex {
+        Complex {
+            re: self.re - other.re,
+            im: self.im - other.im,
+        }
+    }
+
+    fn mul(self, other: Complex) -> Complex {
+        Complex {
+            re: self.re * other.re - self.im * other.im,
+            im: self.re * other.im + self.im * other.re,
+        }
+    }
+
+    fn div(self, other: Complex) -> Complex {
+        let denom = other.re * other.re + other.im * other.im;
+        Complex {
+            re: (self.re * other.re + self.im * other.im) / denom,
+            im: (self.im * other.re - self.re * other.im) / denom,
+        }
+    }
+
+    fn neg(self) -> Complex {
+        Complex {
+            re: -self.re,
+            im: -self.im,
+        }
+    }
+
+    fn is_zero(self) -> bool {
+        self.re == 0.0 && self.im == 0.0
+    }
+}
+
+impl fmt::Display for Complex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.im == 0.0 {
+            write!(f, "{}", self.re)
+        } else if self.im < 0.0 {
+            write!(f, "{} - {}i", self.re, -self.im)
+        } else {
+            write!(f, "{} + {}i", self.re, self.im)
+        }
+    }
+}
+
+/// The numeric domain of the evaluator: exact rationals, widening to
+/// floating-point complex numbers as soon as an imaginary operand appears.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Number {
+    Rational(Rational),
+    Complex(Complex),
+}
+
+impl Number {
+    fn to_complex(self) -> Complex {
+        match self {
+            Number::Rational(r) => Complex {
+                re: r.num as f64 / r.den as f64,
+                im: 0.0,
+            },
+            Number::Complex(c) => c,
+        }
+    }
+
+    fn is_zero(self) -> bool {
+        match self {
+            Number::Rational(r) => r.is_zero(),
+            Number::Complex(c) => c.is_zero(),
+        }
+    }
+
+    fn checked_add(self, other: Number) -> Option<Number> {
+        match (self, other) {
+            (Number::Rational(a), Number::Rational(b)) => a.checked_add(b).map(Number::Rational),
+            (a, b) => Some(Number::Complex(a.to_complex().add(b.to_complex()))),
+        }
+    }
+
+    fn checked_sub(self, other: Number) -> Option<Number> {
+        match (self, other) {
+            (Number::Rational(a), Number::Rational(b)) => a.checked_sub(b).map(Number::Rational),
+            (a, b) => Some(Number::Complex(a.to_complex().sub(b.to_complex()))),
+        }
+    }
+
+    fn checked_mul(self, other: Number) -> Option<Number> {
+        match (self, other) {
+            (Number::Rational(a), Number::Rational(b)) => a.checked_mul(b).map(Number::Rational),
+            (a, b) => Some(Number::Complex(a.to_complex().mul(b.to_complex()))),
+        }
+    }
+
+    fn checked_div(self, other: Number) -> Option<Number> {
+        match (self, other) {
+            (Number::Rational(a), Number::Rational(b)) => a.checked_div(b).map(Number::Rational),
+            (a, b) => Some(Number::Complex(a.to_complex().div(b.to_complex()))),
+        }
+    }
+
+    fn checked_neg(self) -> Option<Number> {
+        match self {
+            Number::Rational(r) => r.checked_neg().map(Number::Rational),
+            Number::Complex(c) => Some(Number::Complex(c.neg())),
+        }
+    }
+
+    fn checked_pow(self, exponent: u32) -> Option<Number> {
+        let mut result = Number::Rational(Rational::from_int(1));
+        let mut base = self;
+        let mut exp = exponent;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.checked_mul(base)?;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                // Only needed for a further iteration — squaring it here
+                // when `exp` has just become 0 would overflow for some
+                // inputs whose actual (in-range) result doesn't need it.
+                base = base.checked_mul(base)?;
+            }
+        }
+        Some(result)
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Number::Rational(r) => write!(f, "{}", r),
+            Number::Complex(c) => write!(f, "{}", c),
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 enum Node {
-    Literal(i32),
+    Literal(Number),
+    Variable(String),
+    Assignment(String, Box<Node>),
     BinOp(Operation, Box<Node>, Box<Node>),
+    UnaryOp(UnaryOperation, Box<Node>),
 }
 
 #[derive(Debug, Clone, PartialEq, Copy)]
@@ -12,134 +248,414 @@ enum Operation {
     Subtract,
     Multiply,
     Divide,
+    Power,
 }
 
 #[derive(Debug, Clone, PartialEq, Copy)]
+enum UnaryOperation {
+    Negate,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 enum Token {
-    Number(i32),
+    Number(Rational),
+    /// A numeric coefficient of the imaginary unit, e.g. `3i` or bare `i` (coefficient 1).
+    Imaginary(Rational),
+    Identifier(String),
     Operator(Operation),
+    Equals,
     LeftParen,
     RightParen,
 }
 
-fn tokenise(input: &str) -> Vec<Token> {
+/// A half-open byte range `[start, end)` into the original source string.
+#[derive(Debug, Clone, PartialEq, Copy)]
+struct Span {
+    start: usize,
+    end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ParseErrorKind {
+    InvalidChar(char),
+    UnexpectedToken(Token),
+    UnexpectedEof,
+    ExpectedRightParen,
+    LiteralOverflow,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ParseError {
+    kind: ParseErrorKind,
+    span: Span,
+}
+
+impl ParseError {
+    fn message(&self) -> String {
+        match &self.kind {
+            ParseErrorKind::InvalidChar(c) => format!("invalid token '{}'", c),
+            ParseErrorKind::UnexpectedToken(token) => format!("unexpected token {:?}", token),
+            ParseErrorKind::UnexpectedEof => "unexpected end of input".to_string(),
+            ParseErrorKind::ExpectedRightParen => "expected ')'".to_string(),
+            ParseErrorKind::LiteralOverflow => "numeric literal too large".to_string(),
+        }
+    }
+}
+
+/// Renders `src` with a `^^^` underline beneath the offending span, followed
+/// by a `gcc`-style "<message> at column N" line.
+fn render_error(src: &str, err: &ParseError) -> String {
+    let underline: String = " ".repeat(err.span.start) + &"^".repeat((err.span.end - err.span.start).max(1));
+    format!("{}\n{}\n{} at column {}", src, underline, err.message(), err.span.start + 1)
+}
+
+fn eof_span(tokens: &[(Token, Span)]) -> Span {
+    let end = tokens.last().map(|(_, span)| span.end).unwrap_or(0);
+    Span { start: end, end }
+}
+
+fn tokenise(input: &str) -> Result<Vec<(Token, Span)>, ParseError> {
     let mut tokens = Vec::new();
-    let mut iter = input.chars().peekable();
+    let mut iter = input.char_indices().peekable();
 
-    while let Some(&c) = iter.peek() {
+    while let Some(&(start, c)) = iter.peek() {
         if c.is_digit(10) {
-            let mut value = c.to_digit(10).unwrap() as i32;
+            let mut value: i128 = c.to_digit(10).unwrap() as i128;
+            let mut den: i128 = 1;
+            let mut end = start + c.len_utf8();
             iter.next();
-            while let Some(&c) = iter.peek() {
+            while let Some(&(idx, c)) = iter.peek() {
                 if c.is_digit(10) {
-                    value = value * 10 + c.to_digit(10).unwrap() as i32;
+                    let digit_end = idx + c.len_utf8();
+                    value = value
+                        .checked_mul(10)
+                        .and_then(|v| v.checked_add(c.to_digit(10).unwrap() as i128))
+                        .ok_or(ParseError {
+                            kind: ParseErrorKind::LiteralOverflow,
+                            span: Span { start, end: digit_end },
+                        })?;
+                    end = digit_end;
                     iter.next();
                 } else {
                     break;
                 }
             }
-            tokens.push(Token::Number(value));
+
+            let mut lookahead = iter.clone();
+            let has_fraction = matches!(lookahead.next(), Some((_, '.')))
+                && matches!(lookahead.peek(), Some((_, c)) if c.is_digit(10));
+            if has_fraction {
+                iter.next(); // consume '.'
+                while let Some(&(idx, c)) = iter.peek() {
+                    if c.is_digit(10) {
+                        let digit_end = idx + c.len_utf8();
+                        let overflow = ParseError {
+                            kind: ParseErrorKind::LiteralOverflow,
+                            span: Span { start, end: digit_end },
+                        };
+                        value = value
+                            .checked_mul(10)
+                            .and_then(|v| v.checked_add(c.to_digit(10).unwrap() as i128))
+                            .ok_or(overflow.clone())?;
+                        den = den.checked_mul(10).ok_or(overflow)?;
+                        end = digit_end;
+                        iter.next();
+                    } else {
+                        break;
+                    }
+                }
+            }
+
+            let value = Rational::new(value, den);
+
+            // An `i` directly after a number (not the start of a longer
+            // identifier, e.g. `3in`) makes it an imaginary coefficient.
+            let mut lookahead = iter.clone();
+            if let Some((idx, 'i')) = lookahead.next() {
+                let continues_identifier =
+                    matches!(lookahead.peek(), Some((_, c)) if c.is_alphanumeric() || *c == '_');
+                if !continues_identifier {
+                    iter.next();
+                    tokens.push((
+                        Token::Imaginary(value),
+                        Span {
+                            start,
+                            end: idx + 'i'.len_utf8(),
+                        },
+                    ));
+                    continue;
+                }
+            }
+
+            tokens.push((Token::Number(value), Span { start, end }));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut name = String::new();
+            let mut end = start;
+            while let Some(&(idx, c)) = iter.peek() {
+                if c.is_alphanumeric() || c == '_' {
+                    name.push(c);
+                    end = idx + c.len_utf8();
+                    iter.next();
+                } else {
+                    break;
+                }
+            }
+            let span = Span { start, end };
+            if name == "i" {
+                tokens.push((Token::Imaginary(Rational::from_int(1)), span));
+            } else {
+                tokens.push((Token::Identifier(name), span));
+            }
         } else {
+            let span = Span {
+                start,
+                end: start + c.len_utf8(),
+            };
             match c {
                 '+' => {
-                    tokens.push(Token::Operator(Operation::Add));
+                    tokens.push((Token::Operator(Operation::Add), span));
                     iter.next();
                 }
                 '-' => {
-                    tokens.push(Token::Operator(Operation::Subtract));
+                    tokens.push((Token::Operator(Operation::Subtract), span));
                     iter.next();
                 }
                 '*' => {
-                    tokens.push(Token::Operator(Operation::Multiply));
+                    tokens.push((Token::Operator(Operation::Multiply), span));
                     iter.next();
                 }
                 '/' => {
-                    tokens.push(Token::Operator(Operation::Divide));
+                    tokens.push((Token::Operator(Operation::Divide), span));
+                    iter.next();
+                }
+                '^' => {
+                    tokens.push((Token::Operator(Operation::Power), span));
+                    iter.next();
+                }
+                '=' => {
+                    tokens.push((Token::Equals, span));
                     iter.next();
                 }
                 '(' => {
-                    tokens.push(Token::LeftParen);
+                    tokens.push((Token::LeftParen, span));
                     iter.next();
                 }
                 ')' => {
-                    tokens.push(Token::RightParen);
+                    tokens.push((Token::RightParen, span));
                     iter.next();
                 }
                 _ if c.is_whitespace() => {
                     iter.next();
                 }
-                _ => panic!("Invalid token: {}", c),
+                _ => {
+                    return Err(ParseError {
+                        kind: ParseErrorKind::InvalidChar(c),
+                        span,
+                    })
+                }
             }
         }
     }
 
-    tokens
+    Ok(tokens)
 }
 
-fn parse_expression(tokens: &[Token], index: usize) -> (Node, usize) {
-    let (mut lhs, mut next_index) = parse_term(tokens, index);
+// Binding powers for infix operators: (left_bp, right_bp). A higher left_bp
+// binds tighter; right_bp < left_bp makes an operator right-associative
+// (used for Power so `2 ^ 3 ^ 2` parses as `2 ^ (3 ^ 2)`).
+fn infix_binding_power(op: Operation) -> (u8, u8) {
+    match op {
+        Operation::Add | Operation::Subtract => (1, 2),
+        Operation::Multiply | Operation::Divide => (3, 4),
+        Operation::Power => (6, 5),
+    }
+}
 
-    while next_index < tokens.len() {
-        match tokens[next_index] {
-            Token::Operator(op) => {
-                let (rhs, next_next_index) = parse_term(tokens, next_index + 1);
-                lhs = Node::BinOp(op, Box::new(lhs), Box::new(rhs));
-                next_index = next_next_index;
-            }
-            Token::RightParen => break,
-            _ => panic!("Unexpected token: {:?}", tokens[next_index]),
+const UNARY_MINUS_BP: u8 = 7;
+
+fn parse_expr(tokens: &[(Token, Span)], pos: &mut usize, min_bp: u8) -> Result<Node, ParseError> {
+    let mut lhs = parse_prefix(tokens, pos)?;
+
+    while let Some((Token::Operator(op), _)) = tokens.get(*pos) {
+        let op = *op;
+        let (left_bp, right_bp) = infix_binding_power(op);
+        if left_bp < min_bp {
+            break;
         }
+
+        *pos += 1;
+        let rhs = parse_expr(tokens, pos, right_bp)?;
+        lhs = Node::BinOp(op, Box::new(lhs), Box::new(rhs));
     }
 
-    (lhs, next_index)
+    Ok(lhs)
 }
 
-fn parse_term(tokens: &[Token], index: usize) -> (Node, usize) {
-    let (mut lhs, mut next_index) = parse_factor(tokens, index);
-
-    while next_index < tokens.len() {
-        match tokens[next_index] {
-            Token::Operator(Operation::Multiply) | Token::Operator(Operation::Divide) => {
-                let op = match tokens[next_index] {
-                    Token::Operator(op) => op,
-                    _ => unreachable!(),
-                };
-
-                let (rhs, next_next_index) = parse_factor(tokens, next_index + 1);
-                lhs = Node::BinOp(op, Box::new(lhs), Box::new(rhs));
-                next_index = next_next_index;
+fn parse_prefix(tokens: &[(Token, Span)], pos: &mut usize) -> Result<Node, ParseError> {
+    match tokens.get(*pos) {
+        Some((Token::Number(value), _)) => {
+            let value = *value;
+            *pos += 1;
+            Ok(Node::Literal(Number::Rational(value)))
+        }
+        Some((Token::Imaginary(coefficient), _)) => {
+            let coefficient = *coefficient;
+            *pos += 1;
+            Ok(Node::Literal(Number::Complex(Complex {
+                re: 0.0,
+                im: coefficient.num as f64 / coefficient.den as f64,
+            })))
+        }
+        Some((Token::Identifier(name), _)) => {
+            let name = name.clone();
+            *pos += 1;
+            Ok(Node::Variable(name))
+        }
+        Some((Token::Operator(Operation::Subtract), _)) => {
+            *pos += 1;
+            let operand = parse_expr(tokens, pos, UNARY_MINUS_BP)?;
+            Ok(Node::UnaryOp(UnaryOperation::Negate, Box::new(operand)))
+        }
+        Some((Token::LeftParen, _)) => {
+            *pos += 1;
+            let expr = parse_expr(tokens, pos, 0)?;
+            match tokens.get(*pos) {
+                Some((Token::RightParen, _)) => {
+                    *pos += 1;
+                    Ok(expr)
+                }
+                Some((_, span)) => Err(ParseError {
+                    kind: ParseErrorKind::ExpectedRightParen,
+                    span: *span,
+                }),
+                None => Err(ParseError {
+                    kind: ParseErrorKind::ExpectedRightParen,
+                    span: eof_span(tokens),
+                }),
             }
-            _ => break,
         }
+        Some((token, span)) => Err(ParseError {
+            kind: ParseErrorKind::UnexpectedToken(token.clone()),
+            span: *span,
+        }),
+        None => Err(ParseError {
+            kind: ParseErrorKind::UnexpectedEof,
+            span: eof_span(tokens),
+        }),
     }
+}
 
-    (lhs, next_index)
+fn expect_fully_consumed(tokens: &[(Token, Span)], pos: usize, node: Node) -> Result<Node, ParseError> {
+    match tokens.get(pos) {
+        None => Ok(node),
+        Some((token, span)) => Err(ParseError {
+            kind: ParseErrorKind::UnexpectedToken(token.clone()),
+            span: *span,
+        }),
+    }
 }
 
-fn parse_factor(tokens: &[Token], index: usize) -> (Node, usize) {
-    match tokens[index] {
-        Token::Number(value) => (Node::Literal(value), index + 1),
-        Token::LeftParen => {
-            let (expr, next_index) = parse_expression(tokens, index + 1);
-            if tokens[next_index] == Token::RightParen {
-                (expr, next_index + 1)
-            } else {
-                panic!("Expected ')'");
-            }
+fn build_ast(input: &str) -> Result<Node, ParseError> {
+    let tokens = tokenise(input)?;
+
+    if let (Some((Token::Identifier(name), _)), Some((Token::Equals, _))) =
+        (tokens.first(), tokens.get(1))
+    {
+        let name = name.clone();
+        let mut pos = 2;
+        let value = parse_expr(&tokens, &mut pos, 0)?;
+        return expect_fully_consumed(&tokens, pos, Node::Assignment(name, Box::new(value)));
+    }
+
+    let mut pos = 0;
+    let expr = parse_expr(&tokens, &mut pos, 0)?;
+    expect_fully_consumed(&tokens, pos, expr)
+}
+
+type Env = HashMap<String, Number>;
+
+#[derive(Debug, Clone, PartialEq)]
+enum EvalError {
+    DivisionByZero,
+    Overflow,
+    UndefinedVariable(String),
+    UnsupportedExponent,
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalError::DivisionByZero => write!(f, "division by zero"),
+            EvalError::Overflow => write!(f, "overflow"),
+            EvalError::UndefinedVariable(name) => write!(f, "undefined variable: {}", name),
+            EvalError::UnsupportedExponent => write!(
+                f,
+                "unsupported exponent (must be a non-negative whole number)"
+            ),
         }
-        _ => panic!("Unexpected token: {:?}", tokens[index]),
     }
 }
 
-fn build_ast(input: &str) -> Node {
-    let tokens = tokenise(input);
-    let (ast, _) = parse_expression(&tokens, 0);
-    ast
+fn evaluate(node: &Node, env: &mut Env) -> Result<Number, EvalError> {
+    match node {
+        Node::Literal(value) => Ok(*value),
+        Node::Variable(name) => env
+            .get(name)
+            .copied()
+            .ok_or_else(|| EvalError::UndefinedVariable(name.clone())),
+        Node::Assignment(name, expr) => {
+            let value = evaluate(expr, env)?;
+            env.insert(name.clone(), value);
+            Ok(value)
+        }
+        Node::UnaryOp(UnaryOperation::Negate, operand) => {
+            evaluate(operand, env)?.checked_neg().ok_or(EvalError::Overflow)
+        }
+        Node::BinOp(op, lhs, rhs) => {
+            let lhs = evaluate(lhs, env)?;
+            let rhs = evaluate(rhs, env)?;
+            match op {
+                Operation::Add => lhs.checked_add(rhs).ok_or(EvalError::Overflow),
+                Operation::Subtract => lhs.checked_sub(rhs).ok_or(EvalError::Overflow),
+                Operation::Multiply => lhs.checked_mul(rhs).ok_or(EvalError::Overflow),
+                Operation::Divide => {
+                    if rhs.is_zero() {
+                        Err(EvalError::DivisionByZero)
+                    } else {
+                        lhs.checked_div(rhs).ok_or(EvalError::Overflow)
+                    }
+                }
+                Operation::Power => {
+                    // A Complex exponent that is really a non-negative whole
+                    // number (e.g. produced by `4i / 4i`) is accepted too.
+                    let exponent = match rhs {
+                        Number::Rational(r) if r.den == 1 && r.num >= 0 => Some(r.num),
+                        Number::Complex(c) if c.im == 0.0 && c.re >= 0.0 && c.re.fract() == 0.0 => {
+                            Some(c.re as i128)
+                        }
+                        _ => None,
+                    }
+                    .ok_or(EvalError::UnsupportedExponent)?;
+                    let exponent = u32::try_from(exponent).map_err(|_| EvalError::Overflow)?;
+                    lhs.checked_pow(exponent).ok_or(EvalError::Overflow)
+                }
+            }
+        }
+    }
 }
 
 fn visualise_ast(node: &Node, level: usize) {
     match node {
         Node::Literal(value) => println!("{}- Literal({})", "|   ".repeat(level), value),
+        Node::Variable(name) => println!("{}- Variable({})", "|   ".repeat(level), name),
+        Node::Assignment(name, expr) => {
+            println!("{}- Assignment({})", "|   ".repeat(level), name);
+            visualise_ast(expr, level + 1);
+        }
+        Node::UnaryOp(op, operand) => {
+            println!("{}- UnaryOp({:?})", "|   ".repeat(level), op);
+            visualise_ast(operand, level + 1);
+        }
         Node::BinOp(op, lhs, rhs) => {
             println!("{}- BinOp({:?})", "|   ".repeat(level), op);
             visualise_ast(lhs, level + 1);
@@ -149,13 +665,173 @@ fn visualise_ast(node: &Node, level: usize) {
 }
 
 fn main() {
-    println!("Enter an expression to parse:");
-    let mut input = String::new();
-    io::stdin()
-        .read_line(&mut input)
-        .expect("Failed to read line");
-
-    let input = input.trim(); // Remove trailing newline
-    let ast = build_ast(input);
-    visualise_ast(&ast, 0);
+    println!("Enter expressions to evaluate (Ctrl-D to exit). Bindings persist across lines.");
+    let stdin = io::stdin();
+    let mut env: Env = HashMap::new();
+    let mut line = String::new();
+
+    loop {
+        print!("> ");
+        io::stdout().flush().expect("Failed to flush stdout");
+
+        line.clear();
+        let bytes_read = stdin.read_line(&mut line).expect("Failed to read line");
+        if bytes_read == 0 {
+            break; // EOF
+        }
+
+        let input = line.trim();
+        if input.is_empty() {
+            continue;
+        }
+
+        let ast = match build_ast(input) {
+            Ok(ast) => ast,
+            Err(err) => {
+                println!("{}", render_error(input, &err));
+                continue;
+            }
+        };
+        visualise_ast(&ast, 0);
+
+        match evaluate(&ast, &mut env) {
+            Ok(result) => println!("= {}", result),
+            Err(err) => println!("error: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(input: &str) -> Result<Number, EvalError> {
+        let mut env = Env::new();
+        evaluate(&build_ast(input).unwrap(), &mut env)
+    }
+
+    fn rational(num: i128, den: i128) -> Number {
+        Number::Rational(Rational::new(num, den))
+    }
+
+    #[test]
+    fn rational_reduces_to_lowest_terms() {
+        assert_eq!(Rational::new(2, 4), Rational::new(1, 2));
+        assert_eq!(Rational::new(-2, 4), Rational::new(1, -2));
+    }
+
+    #[test]
+    fn rational_checked_arithmetic() {
+        let half = Rational::new(1, 2);
+        let third = Rational::new(1, 3);
+        assert_eq!(half.checked_add(third), Some(Rational::new(5, 6)));
+        assert_eq!(half.checked_sub(third), Some(Rational::new(1, 6)));
+        assert_eq!(half.checked_mul(third), Some(Rational::new(1, 6)));
+        assert_eq!(half.checked_div(third), Some(Rational::new(3, 2)));
+        assert_eq!(half.checked_neg(), Some(Rational::new(-1, 2)));
+    }
+
+    #[test]
+    fn rational_checked_arithmetic_overflows() {
+        let huge = Rational::from_int(i128::MAX);
+        assert_eq!(huge.checked_add(Rational::from_int(1)), None);
+        assert_eq!(huge.checked_mul(huge), None);
+    }
+
+    #[test]
+    fn number_checked_pow_large_exponent_stays_in_range() {
+        // Regression test for the exponentiation-by-squaring bug where
+        // `base` was squared unconditionally on the final loop iteration,
+        // making in-range results like `2^64` report a spurious overflow.
+        assert_eq!(
+            rational(2, 1).checked_pow(64),
+            Some(rational(18446744073709551616, 1))
+        );
+        assert_eq!(rational(5, 1).checked_pow(41), eval("5^41").ok());
+    }
+
+    #[test]
+    fn number_checked_pow_overflows_out_of_range() {
+        assert_eq!(Number::Rational(Rational::from_int(i128::MAX)).checked_pow(2), None);
+    }
+
+    #[test]
+    fn evaluate_basic_arithmetic() {
+        assert_eq!(eval("1 + 2 * 3"), Ok(rational(7, 1)));
+        assert_eq!(eval("2 ^ 3 ^ 2"), Ok(rational(512, 1)));
+        assert_eq!(eval("1/3 + 1/3 + 1/3"), Ok(rational(1, 1)));
+        assert_eq!(eval("-3 * 4"), Ok(rational(-12, 1)));
+    }
+
+    #[test]
+    fn evaluate_assignment_persists_across_calls() {
+        let mut env = Env::new();
+        evaluate(&build_ast("x = 3").unwrap(), &mut env).unwrap();
+        assert_eq!(
+            evaluate(&build_ast("x * 2").unwrap(), &mut env),
+            Ok(rational(6, 1))
+        );
+    }
+
+    #[test]
+    fn evaluate_undefined_variable() {
+        assert_eq!(
+            eval("foo"),
+            Err(EvalError::UndefinedVariable("foo".to_string()))
+        );
+    }
+
+    #[test]
+    fn evaluate_division_by_zero() {
+        assert_eq!(eval("1 / 0"), Err(EvalError::DivisionByZero));
+    }
+
+    #[test]
+    fn evaluate_power_overflow() {
+        assert_eq!(eval("2 ^ 99999999999"), Err(EvalError::Overflow));
+    }
+
+    #[test]
+    fn evaluate_power_rejects_non_whole_exponent() {
+        assert_eq!(eval("2 ^ 0.5"), Err(EvalError::UnsupportedExponent));
+        assert_eq!(eval("2 ^ -1"), Err(EvalError::UnsupportedExponent));
+    }
+
+    #[test]
+    fn evaluate_complex_arithmetic() {
+        assert_eq!(
+            eval("(1 + i) * (1 - i)"),
+            Ok(Number::Complex(Complex { re: 2.0, im: 0.0 }))
+        );
+    }
+
+    #[test]
+    fn build_ast_rejects_invalid_char() {
+        let err = build_ast("1 + @").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::InvalidChar('@'));
+    }
+
+    #[test]
+    fn build_ast_rejects_unexpected_eof() {
+        let err = build_ast("1 +").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn build_ast_rejects_missing_right_paren() {
+        let err = build_ast("(1 + 2").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::ExpectedRightParen);
+    }
+
+    #[test]
+    fn build_ast_rejects_literal_overflow() {
+        let err = build_ast("99999999999999999999999999999999999999999999").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::LiteralOverflow);
+    }
+
+    #[test]
+    fn build_ast_rejects_trailing_tokens() {
+        let err = build_ast("1 2").unwrap_err();
+        assert!(matches!(err.kind, ParseErrorKind::UnexpectedToken(_)));
+    }
 }